@@ -0,0 +1,127 @@
+use ethers::{
+    middleware::{
+        gas_oracle::{GasOracleMiddleware, ProviderOracle},
+        NonceManagerMiddleware, SignerMiddleware,
+    },
+    prelude::abigen,
+    providers::{Http, Middleware, Provider},
+};
+use ethers_core::types::{
+    transaction::eip2718::TypedTransaction, Address, Bytes, Eip1559TransactionRequest, H160, H256,
+    U256,
+};
+use ethers_signers::Signer;
+use std::{error::Error, str::FromStr, sync::Arc};
+
+use crate::domain::ChainConfig;
+use crate::sign_data::Presale;
+
+abigen!(IPoolManager, "./src/abi/PoolManager.json");
+
+type BroadcastClient<S> =
+    SignerMiddleware<NonceManagerMiddleware<GasOracleMiddleware<Provider<Http>, ProviderOracle<Provider<Http>>>>, S>;
+
+/// Builds the middleware stack used to broadcast a signed presale permit.
+///
+/// Connects to `rpc_url`, the RPC endpoint of the chain the permit was
+/// actually signed for (see `ChainConfig`), not a single global endpoint
+/// shared across every chain id a request might target.
+///
+/// Layers, outermost first: a signer layer (`wallet`, so outgoing
+/// transactions are signed the same way the permit itself was), a
+/// nonce-manager layer (tracks and increments `wallet`'s nonce so concurrent
+/// `/sign` calls with `broadcast: true` don't collide), and a gas-oracle
+/// layer (fills `max_fee_per_gas`/`max_priority_fee_per_gas` from
+/// `eth_feeHistory` via the provider itself).
+async fn build_client<S: Signer + Clone>(
+    wallet: S,
+    rpc_url: &str,
+) -> Result<BroadcastClient<S>, Box<dyn Error>> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let oracle = ProviderOracle::new(provider.clone());
+    let provider = GasOracleMiddleware::new(provider, oracle);
+    let address = wallet.address();
+    let provider = NonceManagerMiddleware::new(provider, address);
+    Ok(SignerMiddleware::new(provider, wallet))
+}
+
+/// ABI-encodes a `submitPermit(presale..., owner, signature)` call against
+/// the configured pool-manager contract.
+///
+/// Built the same way `sign_data::get_token_info` talks to an ERC20: a typed
+/// binding generated by `abigen!` from an ABI file, not a hand-assembled
+/// calldata blob. `signature` is the hex-encoded EIP-712 signature `sign`
+/// already produced, parsed into the raw bytes the contract's `signature`
+/// parameter expects.
+fn encode_submit_permit(
+    pool_manager: Address,
+    client: Arc<impl Middleware>,
+    presale: &Presale,
+    owner: Address,
+    signature: Bytes,
+) -> Result<Bytes, Box<dyn Error>> {
+    let currency = Address::from(H160::from_str(&presale.currency)?);
+    let contract = IPoolManager::new(pool_manager, client);
+
+    contract
+        .submit_permit(
+            currency,
+            presale.presale_rate,
+            presale.softcap,
+            presale.hardcap,
+            presale.min_buy,
+            presale.max_buy,
+            U256::from(presale.liquidity_rate),
+            presale.listing_rate,
+            U256::from(presale.start_time),
+            U256::from(presale.end_time),
+            U256::from(presale.lock_end_time),
+            presale.is_vesting,
+            presale.is_lock,
+            presale.refund,
+            presale.auto_listing,
+            owner,
+            signature,
+        )
+        .calldata()
+        .ok_or_else(|| "failed to ABI-encode submitPermit call".into())
+}
+
+/// Submits a signed presale permit to `chain`'s pool-manager contract as an
+/// EIP-1559 transaction on `chain_id` and returns the resulting transaction
+/// hash.
+///
+/// This is what backs the `broadcast: true` flag on `/sign`: once a permit
+/// has been signed, the caller can have the service relay it on-chain
+/// instead of returning the bare signature for the caller to submit itself.
+///
+/// `chain` supplies both the RPC endpoint to connect to and the
+/// pool-manager address to call - the same chain the permit was signed for
+/// via `DomainConfig::domain_for_chain` - since a pool-manager deployed on
+/// one chain is essentially never at the same address (or reachable through
+/// the same node) on another. `chain_id` is additionally set directly on
+/// the `Eip1559TransactionRequest` rather than left to `SignerMiddleware`'s
+/// fallback to `wallet.chain_id()`, since `wallet` may have been constructed
+/// with an unrelated default chain id (see `PresaleSigner::from_env`'s
+/// `SIGNER_CHAIN_ID`).
+pub async fn broadcast_permit<S: Signer + Clone>(
+    wallet: S,
+    presale: &Presale,
+    owner: Address,
+    signature: Bytes,
+    chain_id: u64,
+    chain: &ChainConfig,
+) -> Result<H256, Box<dyn Error>> {
+    let pool_manager = chain.proxy_manager;
+    let client = Arc::new(build_client(wallet, &chain.rpc_url).await?);
+    let calldata = encode_submit_permit(pool_manager, client.clone(), presale, owner, signature)?;
+
+    let tx = Eip1559TransactionRequest::new()
+        .to(pool_manager)
+        .data(calldata)
+        .chain_id(chain_id);
+    let pending_tx = client
+        .send_transaction(TypedTransaction::Eip1559(tx), None)
+        .await?;
+    Ok(*pending_tx)
+}
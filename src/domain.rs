@@ -0,0 +1,144 @@
+use ethers_core::types::{transaction::eip712::EIP712Domain, Address, H256, U256};
+use std::{collections::BTreeMap, env, error::Error, fmt};
+
+/// Error produced while resolving an [`EIP712Domain`] or [`ChainConfig`] for
+/// a request.
+#[derive(Debug)]
+pub enum DomainError {
+    /// The request's `chainId` isn't in [`DomainConfig::chains`].
+    UnsupportedChainId(u64),
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedChainId(chain_id) => {
+                write!(f, "chain id {chain_id} is not a configured deployment")
+            }
+        }
+    }
+}
+
+impl Error for DomainError {}
+
+/// A single chain's deployment: where to reach the node, which pool-manager
+/// contract to broadcast to, and which contract the EIP-712 signature is
+/// scoped to on that chain.
+///
+/// Pool-manager/permit contracts are essentially never deployed at the same
+/// address on every chain, so these are kept per-chain in
+/// [`DomainConfig::chains`] rather than as single global `RPC_URL` /
+/// `PROXY_MANAGER` / `VERIFYING_CONTRACT` values shared across every chain
+/// id a request might target.
+#[derive(Debug, Clone)]
+pub struct ChainConfig {
+    pub rpc_url: String,
+    pub proxy_manager: Address,
+    pub verifying_contract: Address,
+}
+
+/// The EIP-712 domain this service signs for, loaded once at startup from
+/// the environment rather than hardcoded.
+///
+/// `name`, `version`, and `salt` are shared across every chain (they
+/// describe the signing service itself), while `chains` maps each supported
+/// chain id to that chain's own [`ChainConfig`]. Because EIP-155 makes the
+/// chain id part of the signed payload, every `/sign` request carries its
+/// target `chainId`, and [`DomainConfig::domain_for_chain`] rejects anything
+/// not present in `chains` before a signature is produced.
+#[derive(Debug, Clone)]
+pub struct DomainConfig {
+    pub name: String,
+    pub version: String,
+    pub salt: Option<[u8; 32]>,
+    pub chains: BTreeMap<u64, ChainConfig>,
+}
+
+impl DomainConfig {
+    /// Reads the domain configuration from the environment:
+    ///
+    /// * `DOMAIN_NAME`, `DOMAIN_VERSION` - the signed EIP-712 domain's name/version.
+    /// * `DOMAIN_SALT` - optional `0x`-prefixed 32-byte salt.
+    /// * `CHAIN_IDS` - a comma-separated list of chain ids this service serves.
+    /// * For each chain id in `CHAIN_IDS`, its own deployment:
+    ///   `RPC_URL_<chainId>`, `PROXY_MANAGER_<chainId>`, `VERIFYING_CONTRACT_<chainId>`.
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        let name = env::var("DOMAIN_NAME")?;
+        let version = env::var("DOMAIN_VERSION")?;
+        let salt = match env::var("DOMAIN_SALT") {
+            Ok(salt) => Some(parse_salt(&salt)?),
+            Err(_) => None,
+        };
+
+        let chain_ids = env::var("CHAIN_IDS")?
+            .split(',')
+            .map(|id| id.trim().parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()?;
+
+        let mut chains = BTreeMap::new();
+        for chain_id in chain_ids {
+            let rpc_url = env::var(format!("RPC_URL_{chain_id}"))?;
+            let proxy_manager = env::var(format!("PROXY_MANAGER_{chain_id}"))?.parse::<Address>()?;
+            let verifying_contract =
+                env::var(format!("VERIFYING_CONTRACT_{chain_id}"))?.parse::<Address>()?;
+            chains.insert(
+                chain_id,
+                ChainConfig {
+                    rpc_url,
+                    proxy_manager,
+                    verifying_contract,
+                },
+            );
+        }
+
+        Ok(Self {
+            name,
+            version,
+            salt,
+            chains,
+        })
+    }
+
+    /// Looks up `chain_id`'s [`ChainConfig`], rejecting it with
+    /// [`DomainError::UnsupportedChainId`] if it isn't in `chains`.
+    pub fn chain(&self, chain_id: u64) -> Result<&ChainConfig, DomainError> {
+        self.chains
+            .get(&chain_id)
+            .ok_or(DomainError::UnsupportedChainId(chain_id))
+    }
+
+    /// Builds the [`EIP712Domain`] for `chain_id`, using that chain's own
+    /// `verifying_contract` and rejecting `chain_id` with
+    /// [`DomainError::UnsupportedChainId`] if it isn't configured.
+    pub fn domain_for_chain(&self, chain_id: u64) -> Result<EIP712Domain, DomainError> {
+        let chain = self.chain(chain_id)?;
+
+        Ok(EIP712Domain {
+            name: Some(self.name.clone()),
+            version: Some(self.version.clone()),
+            chain_id: Some(U256::from(chain_id)),
+            verifying_contract: Some(chain.verifying_contract),
+            salt: self.salt,
+        })
+    }
+
+    /// Reports whether `domain` targets a configured chain id and that
+    /// chain's own `verifying_contract`.
+    ///
+    /// Used by the general-purpose `/sign-typed-data` endpoint, which -
+    /// unlike `/sign` - doesn't build its own domain via
+    /// [`Self::domain_for_chain`] and so needs to check a caller-supplied one
+    /// against the same per-chain configuration before signing it.
+    pub fn allows(&self, domain: &EIP712Domain) -> bool {
+        domain
+            .chain_id
+            .and_then(|chain_id| self.chains.get(&chain_id.as_u64()))
+            .map(|chain| domain.verifying_contract == Some(chain.verifying_contract))
+            .unwrap_or(false)
+    }
+}
+
+fn parse_salt(value: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    let salt: H256 = value.parse()?;
+    Ok(salt.0)
+}
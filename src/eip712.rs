@@ -0,0 +1,80 @@
+use ethers_core::types::transaction::eip712::{EIP712Domain, Eip712DomainType, TypedData, Types};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// The `EIP712Domain` struct's own field/type list, as required by every
+/// EIP-712 payload regardless of primary type.
+///
+/// `salt` is only appended when `domain` actually carries one: a wallet or
+/// verifier recomputing the domain type hash from this list must see
+/// exactly the fields present in `domain` itself, or its hash will diverge
+/// from what was signed.
+fn domain_type_fields(domain: &EIP712Domain) -> Vec<Eip712DomainType> {
+    let mut fields = vec![
+        Eip712DomainType {
+            name: "name".parse().unwrap(),
+            r#type: "string".to_string(),
+        },
+        Eip712DomainType {
+            name: "version".parse().unwrap(),
+            r#type: "string".to_string(),
+        },
+        Eip712DomainType {
+            name: "chainId".parse().unwrap(),
+            r#type: "uint256".to_string(),
+        },
+        Eip712DomainType {
+            name: "verifyingContract".parse().unwrap(),
+            r#type: "address".to_string(),
+        },
+    ];
+
+    if domain.salt.is_some() {
+        fields.push(Eip712DomainType {
+            name: "salt".parse().unwrap(),
+            r#type: "bytes32".to_string(),
+        });
+    }
+
+    fields
+}
+
+/// Assembles a `TypedData` payload for an arbitrary EIP-712 struct.
+///
+/// `fields` is the struct's field name -> Solidity type map, in declaration
+/// order, and `message` is the struct's JSON-object representation. This is
+/// the one place that turns a field/type map into `Types`/`TypedData`, so
+/// signing a new struct shape (vesting schedules, governance votes, order
+/// intents, ...) only needs a field/type map and a message, not a
+/// hand-written copy of the `Eip712DomainType` array.
+pub fn build_typed_data(
+    primary_type: &str,
+    fields: &[(&str, &str)],
+    message: Value,
+    domain: EIP712Domain,
+) -> Result<TypedData, Box<dyn Error>> {
+    let field_types = fields
+        .iter()
+        .map(|(name, solidity_type)| Eip712DomainType {
+            name: name.parse().unwrap(),
+            r#type: solidity_type.to_string(),
+        })
+        .collect();
+
+    let mut types: Types = BTreeMap::new();
+    types.insert("EIP712Domain".parse().unwrap(), domain_type_fields(&domain));
+    types.insert(primary_type.parse().unwrap(), field_types);
+
+    let message = match message {
+        Value::Object(map) => BTreeMap::from_iter(map.into_iter()),
+        _ => return Err("EIP-712 message must be a JSON object".into()),
+    };
+
+    Ok(TypedData {
+        domain,
+        types,
+        primary_type: primary_type.parse().unwrap(),
+        message,
+    })
+}
@@ -1,22 +1,63 @@
 use actix_web::{error, post, web, App, Error, HttpResponse, HttpServer};
 use dotenv::dotenv;
-use ethers::prelude::Address;
-use serde::Deserialize;
+use ethers::prelude::{Address, Signature};
+use ethers_core::types::{transaction::eip712::TypedData, H256};
+use ethers_signers::Signer as _;
+use serde::{Deserialize, Serialize};
 use std::env;
 
-use crate::sign_data::{sign, Presale};
+use crate::broadcast::broadcast_permit;
+use crate::domain::DomainConfig;
+use crate::sign_data::{sign, sign_typed_data, verify, Presale};
+use crate::signer::{PresaleSigner, SharedSigner};
+mod broadcast;
+mod domain;
+mod eip712;
+mod serde_utils;
 mod sign_data;
+mod signer;
 
 #[derive(Deserialize)]
 pub struct PresaleRequest {
     pub presale: Presale,
     pub owner: Address,
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    /// When `true`, the signed permit is additionally submitted on-chain to
+    /// the configured pool-manager contract and its transaction hash is
+    /// returned alongside the signature.
+    #[serde(default)]
+    pub broadcast: bool,
+}
+
+#[derive(Serialize)]
+pub struct SignResponse {
+    pub signature: String,
+    #[serde(rename = "txHash", skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    pub presale: Presale,
+    pub owner: Address,
+    pub signature: Signature,
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct VerifyResponse {
+    pub recovered: Address,
+    pub matches: bool,
 }
 
 /// Handles the signing operation for a presale event.
 ///
 /// This function receives the details of a presale and the owner's address, and attempts to sign it.
-/// If the signing operation is successful, the signature is returned in the response body.
+/// If the signing operation is successful, the signature is returned in the response body as
+/// `{signature}`. If the request sets `broadcast: true`, the signed permit is additionally
+/// submitted on-chain and the response becomes `{signature, txHash}`.
 /// In case of a failure, an HTTP BadRequest response is returned with an "overflow" error message.
 ///
 /// # Arguments
@@ -25,7 +66,8 @@ pub struct PresaleRequest {
 ///
 /// # Returns
 ///
-/// * On success, returns `Ok(HttpResponse::Ok().body(signature))` where `signature` is the signed presale details.
+/// * On success, returns `Ok(HttpResponse::Ok().json(response))` where `response` carries the
+///   signature and, if broadcast, the transaction hash.
 /// * On error, returns `Err(error::ErrorBadRequest("overflow"))`.
 ///
 /// # Errors
@@ -33,13 +75,128 @@ pub struct PresaleRequest {
 /// Returns an error if:
 ///
 /// * The `sign` function fails to sign the presale.
+/// * `broadcast` is requested and submitting the transaction fails. This case returns an HTTP
+///   BadGateway response with a "broadcast failed" message instead of the BadRequest above.
 #[post("/sign")]
-async fn signer(data: web::Json<PresaleRequest>) -> Result<HttpResponse, Error> {
+async fn signer(
+    data: web::Json<PresaleRequest>,
+    wallet: web::Data<PresaleSigner>,
+    domain_config: web::Data<DomainConfig>,
+) -> Result<HttpResponse, Error> {
     let body = data.into_inner();
     let presale: Presale = body.presale;
     let owner: Address = body.owner;
-    match sign(presale, owner).await {
-        Ok(signature) => Ok(HttpResponse::Ok().body(signature)),
+    let presale_for_broadcast = presale.clone();
+    let signature = match sign(
+        presale,
+        owner,
+        wallet.get_ref(),
+        domain_config.get_ref(),
+        body.chain_id,
+    )
+    .await
+    {
+        Ok(signature) => signature,
+        Err(_) => return Err(error::ErrorBadRequest("overflow")),
+    };
+
+    if !body.broadcast {
+        return Ok(HttpResponse::Ok().json(SignResponse {
+            signature,
+            tx_hash: None,
+        }));
+    }
+
+    let signature_bytes = match signature.parse() {
+        Ok(signature_bytes) => signature_bytes,
+        Err(_) => return Err(error::ErrorInternalServerError("invalid signature")),
+    };
+    let chain = match domain_config.chain(body.chain_id) {
+        Ok(chain) => chain,
+        Err(_) => return Err(error::ErrorBadRequest("unsupported chain")),
+    };
+    let shared_wallet = SharedSigner(wallet.into_inner());
+    match broadcast_permit(
+        shared_wallet,
+        &presale_for_broadcast,
+        owner,
+        signature_bytes,
+        body.chain_id,
+        chain,
+    )
+    .await
+    {
+        Ok(tx_hash) => Ok(HttpResponse::Ok().json(SignResponse {
+            signature,
+            tx_hash: Some(tx_hash),
+        })),
+        Err(_) => Err(error::ErrorBadGateway("broadcast failed")),
+    }
+}
+
+/// Recovers the signer of a previously issued presale permit and reports
+/// whether it matches this service's own signer.
+///
+/// Also enforces EIP-3607: `owner` is rejected if it is a contract account.
+///
+/// # Errors
+///
+/// Returns an error if the `verify` function fails to recover a signer, for
+/// example because `owner` has on-chain bytecode or `chainId` isn't allowed.
+#[post("/verify")]
+async fn verifier(
+    data: web::Json<VerifyRequest>,
+    wallet: web::Data<PresaleSigner>,
+    domain_config: web::Data<DomainConfig>,
+) -> Result<HttpResponse, Error> {
+    let body = data.into_inner();
+    match verify(
+        body.presale,
+        body.owner,
+        body.signature,
+        domain_config.get_ref(),
+        body.chain_id,
+    )
+    .await
+    {
+        Ok(recovered) => Ok(HttpResponse::Ok().json(VerifyResponse {
+            recovered,
+            matches: recovered == wallet.address(),
+        })),
+        Err(_) => Err(error::ErrorBadRequest("verification failed")),
+    }
+}
+
+/// Signs an arbitrary EIP-712 payload.
+///
+/// Unlike `/sign`, this doesn't assume a `Presale`/`Permit` shape at all: the
+/// request body is the standard wallet EIP-712 JSON (`domain`, `types`,
+/// `primaryType`, `message`), so integrators can sign vesting schedules,
+/// governance votes, order intents, or any other struct without a code
+/// change to this service. It still enforces the same domain allow-list
+/// `/sign` does via [`DomainConfig::allows`], so it can't be used to sign
+/// arbitrary payloads for a chain id or contract this service doesn't serve.
+///
+/// # Errors
+///
+/// Returns an error if `data.domain` isn't allowed by `domain_config`, or if
+/// the signer fails to produce a signature for `data`.
+#[post("/sign-typed-data")]
+async fn typed_data_signer(
+    data: web::Json<TypedData>,
+    wallet: web::Data<PresaleSigner>,
+    domain_config: web::Data<DomainConfig>,
+) -> Result<HttpResponse, Error> {
+    let typed_data = data.into_inner();
+    if !domain_config.allows(&typed_data.domain) {
+        return Err(error::ErrorForbidden("domain not allowed"));
+    }
+
+    match sign_typed_data(typed_data, wallet.get_ref()).await {
+        Ok(signature) => Ok(HttpResponse::Ok().json(SignResponse {
+            signature,
+            tx_hash: None,
+        })),
         Err(_) => Err(error::ErrorBadRequest("overflow")),
     }
 }
@@ -54,8 +211,32 @@ async fn main() -> std::io::Result<()> {
         }
         Err(_) => {}
     }
+
+    let wallet = match PresaleSigner::from_env().await {
+        Ok(wallet) => web::Data::new(wallet),
+        Err(e) => {
+            println!("Failed to build the presale signer: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    let domain_config = match DomainConfig::from_env() {
+        Ok(domain_config) => web::Data::new(domain_config),
+        Err(e) => {
+            println!("Failed to build the EIP-712 domain config: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+
     let server_address = format!("127.0.0.1:{port}");
-    let result = HttpServer::new(|| App::new().service(signer)).bind(server_address);
+    let result = HttpServer::new(move || {
+        App::new()
+            .app_data(wallet.clone())
+            .app_data(domain_config.clone())
+            .service(signer)
+            .service(verifier)
+            .service(typed_data_signer)
+    })
+    .bind(server_address);
     match result {
         Ok(server) => {
             println!("HTTP server successfully started on {}", port);
@@ -0,0 +1,45 @@
+use ethers_core::types::U256;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+
+/// `serde_with` adapter that lets a `U256` field accept either a `0x`-prefixed
+/// hex string or a plain decimal string/number on the way in, and always
+/// serializes back out as a decimal string.
+///
+/// This keeps the JSON API ergonomic for front-ends that may post large
+/// token amounts as hex (to avoid precision loss) while still accepting the
+/// plain decimal form most callers already send.
+pub struct HexOrDecimalU256;
+
+impl<'de> DeserializeAs<'de, U256> for HexOrDecimalU256 {
+    fn deserialize_as<D>(deserializer: D) -> Result<U256, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        match value {
+            serde_json::Value::String(s) => {
+                if let Some(hex) = s.strip_prefix("0x") {
+                    U256::from_str_radix(hex, 16).map_err(serde::de::Error::custom)
+                } else {
+                    U256::from_dec_str(&s).map_err(serde::de::Error::custom)
+                }
+            }
+            serde_json::Value::Number(n) => {
+                U256::from_dec_str(&n.to_string()).map_err(serde::de::Error::custom)
+            }
+            other => Err(serde::de::Error::custom(format!(
+                "expected a hex/decimal string or number, got {other}"
+            ))),
+        }
+    }
+}
+
+impl SerializeAs<U256> for HexOrDecimalU256 {
+    fn serialize_as<S>(source: &U256, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&source.to_string())
+    }
+}
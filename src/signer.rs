@@ -0,0 +1,279 @@
+use async_trait::async_trait;
+use ethers_core::types::{
+    transaction::{eip2718::TypedTransaction, eip712::Eip712},
+    Address, Signature,
+};
+use ethers_signers::{
+    aws::{AwsSigner, AwsSignerError},
+    ledger::{types::HDPath, Ledger, LedgerError},
+    LocalWallet, Signer, WalletError,
+};
+use rusoto_core::Region;
+use rusoto_kms::KmsClient;
+use std::{env, error::Error, fmt, sync::Arc};
+
+/// Selects which key-management backend [`PresaleSigner`] should wrap.
+///
+/// Read from the `SIGNER_BACKEND` environment variable at startup so the
+/// same binary can sign with a local key in development and a hardware
+/// wallet or KMS key in production without a rebuild.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerBackend {
+    /// Raw private key held in the process, parsed from `PRIVATE_KEY`.
+    Local,
+    /// A Ledger hardware wallet reachable over USB.
+    Ledger,
+    /// An AWS KMS asymmetric signing key.
+    Kms,
+}
+
+impl SignerBackend {
+    /// Reads `SIGNER_BACKEND` from the environment, defaulting to `Local`
+    /// when it isn't set.
+    fn from_env() -> Result<Self, Box<dyn Error>> {
+        match env::var("SIGNER_BACKEND") {
+            Ok(backend) => match backend.to_lowercase().as_str() {
+                "local" => Ok(Self::Local),
+                "ledger" => Ok(Self::Ledger),
+                "kms" => Ok(Self::Kms),
+                other => Err(format!("unknown SIGNER_BACKEND '{other}'").into()),
+            },
+            Err(_) => Ok(Self::Local),
+        }
+    }
+}
+
+/// A presale signer backed by either a local private key, a Ledger hardware
+/// wallet, or an AWS KMS key.
+///
+/// Built once at server startup via [`PresaleSigner::from_env`] and shared
+/// through `web::Data`, so the underlying key material (or hardware/KMS
+/// session) is only ever touched once instead of being re-parsed from the
+/// environment on every `/sign` call. Implements ethers' [`Signer`] trait so
+/// it can be passed anywhere a generic `impl Signer` is expected, in
+/// particular to `sign_data::sign`.
+///
+/// Deliberately does not derive `Clone`: the `Ledger` variant wraps a
+/// hardware-transport handle that isn't `Clone`, so a blanket derive would
+/// fail to compile the moment that backend is selected. Code that needs a
+/// cloneable handle to the configured signer (`broadcast::broadcast_permit`,
+/// which requires `Signer + Clone` for its middleware stack) should share a
+/// [`SharedSigner`] instead of cloning a `PresaleSigner` directly.
+#[derive(Debug)]
+pub enum PresaleSigner {
+    Local(LocalWallet),
+    Ledger(Ledger),
+    Kms(AwsSigner),
+}
+
+/// A cheaply-cloneable handle to a [`PresaleSigner`].
+///
+/// Wraps the signer in an `Arc` rather than requiring `PresaleSigner: Clone`,
+/// so it works uniformly across all three backends regardless of whether the
+/// underlying hardware/KMS client happens to implement `Clone` itself.
+/// `with_chain_id` is a no-op here: callers that need a specific chain id on
+/// a signed payload (e.g. `broadcast::broadcast_permit`) set it directly on
+/// the transaction instead, since `SignerMiddleware` only falls back to the
+/// signer's own `chain_id()` when the transaction doesn't already carry one.
+#[derive(Debug, Clone)]
+pub struct SharedSigner(pub Arc<PresaleSigner>);
+
+impl SharedSigner {
+    pub fn new(signer: PresaleSigner) -> Self {
+        Self(Arc::new(signer))
+    }
+}
+
+#[async_trait]
+impl Signer for SharedSigner {
+    type Error = PresaleSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        self.0.sign_message(message).await
+    }
+
+    async fn sign_transaction(
+        &self,
+        message: &TypedTransaction,
+    ) -> Result<Signature, Self::Error> {
+        self.0.sign_transaction(message).await
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        self.0.sign_typed_data(payload).await
+    }
+
+    fn address(&self) -> Address {
+        self.0.address()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.0.chain_id()
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, _chain_id: T) -> Self {
+        self
+    }
+}
+
+/// Error produced while signing through any [`PresaleSigner`] backend.
+#[derive(Debug)]
+pub enum PresaleSignerError {
+    Local(WalletError),
+    Ledger(LedgerError),
+    Kms(AwsSignerError),
+}
+
+impl fmt::Display for PresaleSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Local(e) => write!(f, "local wallet error: {e}"),
+            Self::Ledger(e) => write!(f, "ledger error: {e}"),
+            Self::Kms(e) => write!(f, "AWS KMS signer error: {e}"),
+        }
+    }
+}
+
+impl Error for PresaleSignerError {}
+
+#[async_trait]
+impl Signer for PresaleSigner {
+    type Error = PresaleSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_message(message)
+                .await
+                .map_err(PresaleSignerError::Local),
+            Self::Ledger(ledger) => ledger
+                .sign_message(message)
+                .await
+                .map_err(PresaleSignerError::Ledger),
+            Self::Kms(kms) => kms
+                .sign_message(message)
+                .await
+                .map_err(PresaleSignerError::Kms),
+        }
+    }
+
+    async fn sign_transaction(
+        &self,
+        message: &TypedTransaction,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_transaction(message)
+                .await
+                .map_err(PresaleSignerError::Local),
+            Self::Ledger(ledger) => ledger
+                .sign_transaction(message)
+                .await
+                .map_err(PresaleSignerError::Ledger),
+            Self::Kms(kms) => kms
+                .sign_transaction(message)
+                .await
+                .map_err(PresaleSignerError::Kms),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_typed_data(payload)
+                .await
+                .map_err(PresaleSignerError::Local),
+            Self::Ledger(ledger) => ledger
+                .sign_typed_data(payload)
+                .await
+                .map_err(PresaleSignerError::Ledger),
+            Self::Kms(kms) => kms
+                .sign_typed_data(payload)
+                .await
+                .map_err(PresaleSignerError::Kms),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(wallet) => wallet.address(),
+            Self::Ledger(ledger) => ledger.address(),
+            Self::Kms(kms) => kms.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(wallet) => wallet.chain_id(),
+            Self::Ledger(ledger) => ledger.chain_id(),
+            Self::Kms(kms) => kms.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(wallet) => Self::Local(wallet.with_chain_id(chain_id)),
+            Self::Ledger(ledger) => Self::Ledger(ledger.with_chain_id(chain_id)),
+            Self::Kms(kms) => Self::Kms(kms.with_chain_id(chain_id)),
+        }
+    }
+}
+
+impl PresaleSigner {
+    /// Builds a [`PresaleSigner`] once at startup, selecting the backend via
+    /// `SIGNER_BACKEND` and reading that backend's own configuration from
+    /// the environment:
+    ///
+    /// * `Local` - `PRIVATE_KEY`, a hex-encoded private key.
+    /// * `Ledger` - `LEDGER_DERIVATION_INDEX` (defaults to `0`).
+    /// * `Kms` - `AWS_REGION` and `AWS_KMS_KEY_ID`.
+    ///
+    /// `SIGNER_CHAIN_ID` (defaults to `1`) seeds the Ledger/KMS client's own
+    /// notion of the chain it's signing for. It's informational only: every
+    /// request carries its own target `chainId` (checked against
+    /// `DomainConfig::chains`), and `broadcast::broadcast_permit`
+    /// sets that chain id directly on the transaction it submits rather than
+    /// relying on whichever chain id the signer was constructed with.
+    pub async fn from_env() -> Result<Self, Box<dyn Error>> {
+        let chain_id: u64 = env::var("SIGNER_CHAIN_ID")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(1);
+
+        match SignerBackend::from_env()? {
+            SignerBackend::Local => {
+                let wallet = env::var("PRIVATE_KEY")?
+                    .parse::<LocalWallet>()?
+                    .with_chain_id(chain_id);
+                Ok(Self::Local(wallet))
+            }
+            SignerBackend::Ledger => {
+                let index: usize = env::var("LEDGER_DERIVATION_INDEX")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(0);
+                let ledger = Ledger::new(HDPath::LedgerLive(index), chain_id).await?;
+                Ok(Self::Ledger(ledger))
+            }
+            SignerBackend::Kms => {
+                let region: Region = env::var("AWS_REGION")?.parse()?;
+                let key_id = env::var("AWS_KMS_KEY_ID")?;
+                let client = KmsClient::new(region);
+                let kms = AwsSigner::new(client, key_id, chain_id).await?;
+                Ok(Self::Kms(kms))
+            }
+        }
+    }
+}